@@ -1,8 +1,8 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
-use toml_edit::{table, value, Document, Item};
+use toml_edit::{table, value, Array, Document, Item};
 
 use crate::toml::BuiltinProfile;
 use crate::TomlProfileTemplate;
@@ -13,18 +13,181 @@ pub struct ParsedProfile {
     items: HashMap<String, Item>,
 }
 
+impl ParsedProfile {
+    /// Returns the name of the profile that this profile directly inherits from,
+    /// if it specifies one.
+    fn inherits(&self) -> Option<&str> {
+        self.items.get("inherits").and_then(|item| item.as_str())
+    }
+}
+
+/// The state of a single field compared between the manifest and what a template expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldDiff {
+    Matching,
+    Missing { expected: String },
+    Mismatched { current: String, expected: String },
+}
+
+impl FieldDiff {
+    pub fn is_drifted(&self) -> bool {
+        !matches!(self, FieldDiff::Matching)
+    }
+}
+
+/// The result of comparing a profile already present in a manifest (if any) against a template,
+/// without writing anything. Returned by [`ParsedManifest::diff_profile`].
+#[derive(Debug)]
+pub struct ProfileDiff {
+    pub name: String,
+    pub inherits: Option<FieldDiff>,
+    pub fields: HashMap<String, FieldDiff>,
+}
+
+impl ProfileDiff {
+    /// Returns `true` if the profile has drifted from the template, i.e. `verify` should fail.
+    pub fn is_drifted(&self) -> bool {
+        self.inherits.as_ref().is_some_and(FieldDiff::is_drifted)
+            || self.fields.values().any(FieldDiff::is_drifted)
+    }
+}
+
+/// The severity level assigned to a lint or lint group in a `[lints.*]` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+    Forbid,
+}
+
+impl LintLevel {
+    fn as_str(self) -> &'static str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+            LintLevel::Forbid => "forbid",
+        }
+    }
+}
+
+/// A curated set of `[lints.rust]`/`[lints.clippy]` entries that [`ParsedManifest::apply_lints`]
+/// can write into a manifest, mirroring how [`TomlProfileTemplate`] describes a profile. Lints are
+/// kept in insertion order (rather than a `HashMap`) so the written manifest is deterministic.
+#[derive(Debug, Clone, Default)]
+pub struct LintsTemplate {
+    pub rust: Vec<(String, LintLevel)>,
+    pub clippy: Vec<(String, LintLevel)>,
+    /// Whether to also set `lints.workspace = true`, pulling the rest from `[workspace.lints]`.
+    pub workspace: bool,
+}
+
+/// A strict built-in lint template: denies unsafe operations in an `unsafe fn` body that are not
+/// wrapped in an `unsafe` block (`unsafe_op_in_unsafe_fn`), and warns
+/// on the `clippy::pedantic` and `clippy::nursery` lint groups.
+pub fn strict_lints_template() -> LintsTemplate {
+    let rust = vec![("unsafe_op_in_unsafe_fn".to_string(), LintLevel::Deny)];
+    let clippy = vec![
+        ("pedantic".to_string(), LintLevel::Warn),
+        ("nursery".to_string(), LintLevel::Warn),
+    ];
+
+    LintsTemplate {
+        rust,
+        clippy,
+        workspace: false,
+    }
+}
+
+/// A not-yet-persisted change to the `.cargo-wizard-state.toml` sidecar, produced by
+/// `apply_profile`/`revert_profile` and only written to disk once [`ParsedManifest::write`] is
+/// called, so that the sidecar never drifts out of sync with a manifest write that was skipped or
+/// failed.
+#[derive(Debug)]
+enum PendingStateChange {
+    Applied {
+        name: String,
+        profile_created: bool,
+        added_keys: Vec<String>,
+        overwritten: HashMap<String, String>,
+    },
+    Reverted {
+        name: String,
+    },
+}
+
 #[derive(Debug)]
 pub struct ParsedManifest {
+    path: PathBuf,
     document: Document,
     profiles: HashMap<String, ParsedProfile>,
+    pending_state_change: Option<PendingStateChange>,
 }
 
 impl ParsedManifest {
+    /// Returns `true` if this manifest has a `[workspace]` table, i.e. it is a workspace root
+    /// (possibly also a package manifest) rather than a plain member manifest.
+    pub fn is_workspace(&self) -> bool {
+        self.document.get("workspace").is_some()
+    }
+
+    /// Returns `true` if this manifest is a virtual manifest: a `[workspace]` table with no
+    /// `[package]` of its own.
+    pub fn is_virtual_manifest(&self) -> bool {
+        self.is_workspace() && self.document.get("package").is_none()
+    }
+
+    /// Returns the path of the manifest this was parsed from. If `apply_profile` was called with
+    /// `workspace: true` from a workspace member, the returned [`Self`] is re-rooted at the
+    /// workspace's manifest, so callers should always write back to `path()` rather than to
+    /// whatever path they originally parsed.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Applies `template` under `[profile.<name>]`, merging nested `package.<spec>` and
+    /// `build-override` tables and tracking provenance for `revert_profile`.
+    ///
+    /// Cargo ignores profiles set in a workspace member's manifest, so if this manifest is a
+    /// member of a workspace with a separate root, this either refuses with a helpful error, or,
+    /// if `workspace` is `true`, re-parses the workspace root and applies the profile there
+    /// instead (returning that manifest rather than `self`). A manifest that merely happens to be
+    /// nested under an unrelated workspace's root directory, but is not matched by its `members`
+    /// (or is matched by its `exclude`), is treated as standalone.
     pub fn apply_profile(
         mut self,
         name: &str,
         template: TomlProfileTemplate,
+        workspace: bool,
     ) -> anyhow::Result<Self> {
+        if !self.is_workspace() {
+            let workspace_root_path = find_workspace_manifest_path(&self.path)?;
+            if workspace_root_path != self.path {
+                let workspace_root = parse_manifest(&workspace_root_path)?;
+                if is_workspace_member(&workspace_root, &workspace_root_path, &self.path)? {
+                    if workspace {
+                        return workspace_root.apply_profile(name, template, workspace);
+                    }
+
+                    let root_kind = if workspace_root.is_virtual_manifest() {
+                        "virtual workspace manifest"
+                    } else {
+                        "workspace root manifest"
+                    };
+                    anyhow::bail!(
+                        "`{}` is a member of the workspace rooted at the {root_kind} `{}`. Cargo \
+                         ignores profiles set in member manifests, so apply the profile to the \
+                         workspace root instead, e.g. `cargo wizard apply --workspace`, or \
+                         `cargo wizard apply --manifest-path {}`",
+                        self.path.display(),
+                        workspace_root_path.display(),
+                        workspace_root_path.display()
+                    );
+                }
+            }
+        }
+
         let profiles_table = self
             .document
             .entry("profile")
@@ -33,6 +196,7 @@ impl ParsedManifest {
             .ok_or_else(|| anyhow::anyhow!("The profile item in Cargo.toml is not a table"))?;
         profiles_table.set_dotted(true);
 
+        let profile_created = !profiles_table.contains_key(name);
         let profile_table = profiles_table
             .entry(name)
             .or_insert(table())
@@ -41,11 +205,26 @@ impl ParsedManifest {
                 anyhow::anyhow!("The profile.{name} table in Cargo.toml is not a table")
             })?;
 
+        let previously_added = previously_added_keys(&self.path, name)?;
+
+        let mut added_keys = Vec::new();
+        let mut overwritten = HashMap::new();
+        for key in template.template.fields.keys() {
+            match profile_table.get(key).and_then(|item| item.as_value()) {
+                // A key the wizard already introduced in an earlier `apply_profile` call must stay
+                // tracked as "added", even though it is now present in the table, so that
+                // `revert_profile` still removes it instead of also trying to "restore" it.
+                Some(_) if previously_added.contains(key.as_str()) => added_keys.push(key.clone()),
+                Some(existing) => {
+                    overwritten.insert(key.clone(), existing.to_string().trim().to_string());
+                }
+                None => added_keys.push(key.clone()),
+            }
+        }
+
         if !is_builtin_profile(name) {
-            let inherits = match template.inherits {
-                BuiltinProfile::Dev => "dev",
-                BuiltinProfile::Release => "release",
-            };
+            let inherits = template.inherits.name();
+            resolve_inherits_chain(&self.profiles, inherits)?;
 
             // Add "inherits" as the first key of the table
             let items: Vec<_> = profile_table
@@ -55,30 +234,265 @@ impl ParsedManifest {
             profile_table.clear();
             if !items.iter().any(|(name, _)| *name == "inherits") {
                 profile_table.insert("inherits", value(inherits));
+                added_keys.push("inherits".to_string());
             }
             for (name, item) in items {
                 profile_table.insert(&name, item);
             }
         }
 
-        for (key, val) in &template.template.fields {
-            let mut new_value = val.to_toml_value();
+        merge_fields(
+            profile_table,
+            template
+                .template
+                .fields
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.to_toml_value())),
+        );
+
+        for (spec, overrides) in &template.package_overrides {
+            let package_table = profile_table
+                .entry("package")
+                .or_insert(table())
+                .as_table_mut()
+                .ok_or_else(|| {
+                    anyhow::anyhow!("The profile.{name}.package item in Cargo.toml is not a table")
+                })?;
+            package_table.set_implicit(true);
 
-            if let Some(existing_item) = profile_table.get_mut(key) {
-                if let Some(value) = existing_item.as_value() {
-                    *new_value.decor_mut() = value.decor().clone();
-                }
-                *existing_item = value(new_value);
-            } else {
-                profile_table.insert(key, value(new_value));
+            let spec_table = package_table
+                .entry(spec.as_str())
+                .or_insert(table())
+                .as_table_mut()
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "The profile.{name}.package.{spec} item in Cargo.toml is not a table"
+                    )
+                })?;
+            merge_fields(
+                spec_table,
+                overrides
+                    .fields
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.to_toml_value())),
+            );
+        }
+
+        if let Some(build_override) = &template.build_override {
+            let build_override_table = profile_table
+                .entry("build-override")
+                .or_insert(table())
+                .as_table_mut()
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "The profile.{name}.build-override item in Cargo.toml is not a table"
+                    )
+                })?;
+            merge_fields(
+                build_override_table,
+                build_override
+                    .fields
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.to_toml_value())),
+            );
+        }
+
+        self.pending_state_change = Some(PendingStateChange::Applied {
+            name: name.to_string(),
+            profile_created,
+            added_keys,
+            overwritten,
+        });
+
+        Ok(self)
+    }
+
+    /// Undoes a previous [`Self::apply_profile`] call on profile `name`: removes the keys the
+    /// wizard introduced, restores the values it overwrote, and drops the `[profile.<name>]`
+    /// table entirely if the wizard created it and it ends up empty. Relies on the provenance
+    /// recorded by `apply_profile` in the sidecar state file, so it also works across separate
+    /// invocations. Only the top-level keys of `[profile.<name>]` are tracked: values written into
+    /// its `package.<spec>` and `build-override` subtables are not removed or restored.
+    pub fn revert_profile(mut self, name: &str) -> anyhow::Result<Self> {
+        let state_path = wizard_state_path(&self.path);
+        let state = load_wizard_state(&state_path)?;
+
+        let applied = state
+            .get("profile")
+            .and_then(|p| p.get(name))
+            .cloned()
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No wizard-applied state was recorded for profile `{name}`; nothing to revert"
+                )
+            })?;
+
+        let profiles_table = self
+            .document
+            .entry("profile")
+            .or_insert(table())
+            .as_table_mut()
+            .ok_or_else(|| anyhow::anyhow!("The profile item in Cargo.toml is not a table"))?;
+
+        let profile_table = profiles_table
+            .get_mut(name)
+            .and_then(|item| item.as_table_mut())
+            .ok_or_else(|| {
+                anyhow::anyhow!("Profile `{name}` is not present in Cargo.toml; nothing to revert")
+            })?;
+
+        if let Some(added) = applied.get("added").and_then(|item| item.as_array()) {
+            for key in added.iter().filter_map(|item| item.as_str()) {
+                profile_table.remove(key);
+            }
+        }
+
+        if let Some(overwritten) = applied.get("overwritten").and_then(|item| item.as_table()) {
+            for (key, prev_value) in overwritten.iter() {
+                let prev_value =
+                    prev_value
+                        .as_value()
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("Wizard state file is corrupt for profile `{name}`")
+                        })?;
+                let parsed = prev_value
+                    .parse::<toml_edit::Value>()
+                    .with_context(|| format!("Cannot restore prior value of `{key}`"))?;
+                profile_table.insert(key, value(parsed));
+            }
+        }
+
+        let created = applied
+            .get("created")
+            .and_then(|item| item.as_bool())
+            .unwrap_or(false);
+        if created && profile_table.is_empty() {
+            profiles_table.remove(name);
+        }
+
+        self.pending_state_change = Some(PendingStateChange::Reverted {
+            name: name.to_string(),
+        });
+
+        Ok(self)
+    }
+
+    /// Writes a curated `[lints.rust]`/`[lints.clippy]` (and optionally `lints.workspace = true`)
+    /// configuration into the manifest, using the same decor-preserving merge as `apply_profile`.
+    pub fn apply_lints(mut self, template: &LintsTemplate) -> anyhow::Result<Self> {
+        let lints_table = self
+            .document
+            .entry("lints")
+            .or_insert(table())
+            .as_table_mut()
+            .ok_or_else(|| anyhow::anyhow!("The lints item in Cargo.toml is not a table"))?;
+        lints_table.set_implicit(true);
+
+        if template.workspace {
+            merge_fields(
+                lints_table,
+                std::iter::once(("workspace", toml_edit::Value::from(true))),
+            );
+        }
+
+        for (group_name, fields) in [("rust", &template.rust), ("clippy", &template.clippy)] {
+            if fields.is_empty() {
+                continue;
             }
+
+            let group_table = lints_table
+                .entry(group_name)
+                .or_insert(table())
+                .as_table_mut()
+                .ok_or_else(|| {
+                    anyhow::anyhow!("The lints.{group_name} item in Cargo.toml is not a table")
+                })?;
+            merge_fields(
+                group_table,
+                fields
+                    .iter()
+                    .map(|(lint, level)| (lint.as_str(), toml_edit::Value::from(level.as_str()))),
+            );
         }
 
         Ok(self)
     }
 
-    pub fn write(self, path: &Path) -> anyhow::Result<()> {
+    /// Compares the profile called `name` against what `apply_profile` would write for `template`,
+    /// including its `package.<spec>` and `build-override` subtables, without mutating the
+    /// manifest. Used by the `verify` subcommand to detect drift in CI.
+    pub fn diff_profile(&self, name: &str, template: &TomlProfileTemplate) -> ProfileDiff {
+        let profile = self.profiles.get(name);
+
+        let inherits = if is_builtin_profile(name) {
+            None
+        } else {
+            let expected = template.inherits.name().to_string();
+            Some(match profile.and_then(|p| p.inherits()) {
+                Some(current) if current == expected => FieldDiff::Matching,
+                Some(current) => FieldDiff::Mismatched {
+                    current: current.to_string(),
+                    expected,
+                },
+                None => FieldDiff::Missing { expected },
+            })
+        };
+
+        let mut fields: HashMap<String, FieldDiff> = template
+            .template
+            .fields
+            .iter()
+            .map(|(key, val)| {
+                let expected = val.to_toml_value().to_string().trim().to_string();
+                let current = profile.and_then(|p| p.items.get(key));
+                (key.clone(), diff_field(current, expected))
+            })
+            .collect();
+
+        for (spec, overrides) in &template.package_overrides {
+            let spec_table = profile
+                .and_then(|p| p.items.get("package"))
+                .and_then(|item| item.as_table_like())
+                .and_then(|table| table.get(spec.as_str()));
+            for (key, val) in &overrides.fields {
+                let expected = val.to_toml_value().to_string().trim().to_string();
+                let current = spec_table
+                    .and_then(|item| item.as_table_like())
+                    .and_then(|table| table.get(key));
+                fields.insert(
+                    format!("package.{spec}.{key}"),
+                    diff_field(current, expected),
+                );
+            }
+        }
+
+        if let Some(build_override) = &template.build_override {
+            let build_override_table = profile.and_then(|p| p.items.get("build-override"));
+            for (key, val) in &build_override.fields {
+                let expected = val.to_toml_value().to_string().trim().to_string();
+                let current = build_override_table
+                    .and_then(|item| item.as_table_like())
+                    .and_then(|table| table.get(key));
+                fields.insert(
+                    format!("build-override.{key}"),
+                    diff_field(current, expected),
+                );
+            }
+        }
+
+        ProfileDiff {
+            name: name.to_string(),
+            inherits,
+            fields,
+        }
+    }
+
+    pub fn write(mut self, path: &Path) -> anyhow::Result<()> {
         std::fs::write(path, self.document.to_string())?;
+        if let Some(change) = self.pending_state_change.take() {
+            flush_pending_state_change(&self.path, change)?;
+        }
         Ok(())
     }
 }
@@ -87,6 +501,214 @@ fn is_builtin_profile(name: &str) -> bool {
     matches!(name, "dev" | "release")
 }
 
+/// Path of the sidecar file cargo-wizard uses to remember which keys it introduced or
+/// overwrote in a manifest, so that `revert_profile` keeps working across separate invocations.
+fn wizard_state_path(manifest_path: &Path) -> PathBuf {
+    manifest_path.with_file_name(".cargo-wizard-state.toml")
+}
+
+fn load_wizard_state(state_path: &Path) -> anyhow::Result<Document> {
+    if !state_path.exists() {
+        return Ok(Document::new());
+    }
+    std::fs::read_to_string(state_path)
+        .context("Cannot read cargo-wizard state file")?
+        .parse::<Document>()
+        .context("Cannot parse cargo-wizard state file")
+}
+
+/// Returns the set of keys already recorded as wizard-`added` for profile `name` in the sidecar
+/// state file, so that `apply_profile` can tell a key it introduced on a previous call (now
+/// present in the table) apart from one the user set themselves (which should be `overwritten`).
+fn previously_added_keys(manifest_path: &Path, name: &str) -> anyhow::Result<HashSet<String>> {
+    let state_path = wizard_state_path(manifest_path);
+    let state = load_wizard_state(&state_path)?;
+
+    Ok(state
+        .get("profile")
+        .and_then(|p| p.get(name))
+        .and_then(|entry| entry.get("added"))
+        .and_then(|item| item.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|item| item.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Persists a [`PendingStateChange`] queued up by `apply_profile`/`revert_profile` into the
+/// sidecar state file. Called from [`ParsedManifest::write`] so the sidecar is only ever updated
+/// alongside a successful manifest write.
+fn flush_pending_state_change(
+    manifest_path: &Path,
+    change: PendingStateChange,
+) -> anyhow::Result<()> {
+    match change {
+        PendingStateChange::Applied {
+            name,
+            profile_created,
+            added_keys,
+            overwritten,
+        } => record_applied_profile(
+            manifest_path,
+            &name,
+            profile_created,
+            &added_keys,
+            &overwritten,
+        ),
+        PendingStateChange::Reverted { name } => remove_applied_profile(manifest_path, &name),
+    }
+}
+
+/// Removes the sidecar's record of a previously applied profile, as part of reverting it.
+fn remove_applied_profile(manifest_path: &Path, name: &str) -> anyhow::Result<()> {
+    let state_path = wizard_state_path(manifest_path);
+    let mut state = load_wizard_state(&state_path)?;
+
+    if let Some(profiles) = state.get_mut("profile").and_then(|p| p.as_table_mut()) {
+        profiles.remove(name);
+    }
+
+    std::fs::write(&state_path, state.to_string()).context("Cannot write cargo-wizard state file")
+}
+
+/// Records, in the sidecar state file, which keys `apply_profile` just introduced or overwrote
+/// for profile `name`, so that a later `revert_profile` call can undo them.
+fn record_applied_profile(
+    manifest_path: &Path,
+    name: &str,
+    profile_created: bool,
+    added_keys: &[String],
+    overwritten: &HashMap<String, String>,
+) -> anyhow::Result<()> {
+    if !profile_created && added_keys.is_empty() && overwritten.is_empty() {
+        return Ok(());
+    }
+
+    let state_path = wizard_state_path(manifest_path);
+    let mut state = load_wizard_state(&state_path)?;
+
+    let profiles = state
+        .entry("profile")
+        .or_insert(table())
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("cargo-wizard state file is corrupt"))?;
+    let entry = profiles
+        .entry(name)
+        .or_insert(table())
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("cargo-wizard state file is corrupt"))?;
+
+    if profile_created {
+        entry.insert("created", value(true));
+    }
+
+    if !added_keys.is_empty() {
+        let mut all_added: Vec<String> = entry
+            .get("added")
+            .and_then(|item| item.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|item| item.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        for key in added_keys {
+            if !all_added.contains(key) {
+                all_added.push(key.clone());
+            }
+        }
+        let array: Array = all_added.iter().map(String::as_str).collect();
+        entry.insert("added", value(array));
+    }
+
+    if !overwritten.is_empty() {
+        let overwritten_table = entry
+            .entry("overwritten")
+            .or_insert(table())
+            .as_table_mut()
+            .ok_or_else(|| anyhow::anyhow!("cargo-wizard state file is corrupt"))?;
+        for (key, prev_value) in overwritten {
+            // Only keep the oldest known value, so re-applying a template repeatedly does not
+            // lose track of what was there before the wizard touched this key at all.
+            if !overwritten_table.contains_key(key) {
+                overwritten_table.insert(key, value(prev_value.as_str()));
+            }
+        }
+    }
+
+    std::fs::write(&state_path, state.to_string()).context("Cannot write cargo-wizard state file")
+}
+
+/// Compares a single manifest field against the value a template expects for it.
+fn diff_field(current: Option<&Item>, expected: String) -> FieldDiff {
+    match current {
+        Some(item) => {
+            let current = item
+                .as_value()
+                .map(|v| v.to_string().trim().to_string())
+                .unwrap_or_default();
+            if current == expected {
+                FieldDiff::Matching
+            } else {
+                FieldDiff::Mismatched { current, expected }
+            }
+        }
+        None => FieldDiff::Missing { expected },
+    }
+}
+
+/// Merges `fields` into `table`, overwriting existing keys while keeping their original decor
+/// (whitespace/comments), and appending keys that are not yet present.
+fn merge_fields<'a>(
+    table: &mut toml_edit::Table,
+    fields: impl Iterator<Item = (&'a str, toml_edit::Value)>,
+) {
+    for (key, mut new_value) in fields {
+        if let Some(existing_item) = table.get_mut(key) {
+            if let Some(value) = existing_item.as_value() {
+                *new_value.decor_mut() = value.decor().clone();
+            }
+            *existing_item = value(new_value);
+        } else {
+            table.insert(key, value(new_value));
+        }
+    }
+}
+
+/// Walks the `inherits` chain starting at `start`, following already parsed profiles until a
+/// builtin profile (`dev`/`release`) is reached. Fails if a profile in the chain is missing from
+/// the manifest, or if the chain revisits a profile it has already passed through.
+fn resolve_inherits_chain(
+    profiles: &HashMap<String, ParsedProfile>,
+    start: &str,
+) -> anyhow::Result<()> {
+    let mut current = start;
+    let mut visited = HashSet::new();
+
+    while !is_builtin_profile(current) {
+        if !visited.insert(current.to_string()) {
+            anyhow::bail!(
+                "Profile `{current}` is part of a cycle in the `inherits` chain starting at `{start}`"
+            );
+        }
+
+        let profile = profiles.get(current).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Profile `{current}` is used in an `inherits` chain, but it was not found in Cargo.toml"
+            )
+        })?;
+        current = profile.inherits().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Profile `{current}` does not specify `inherits`, so it cannot be used as a parent profile"
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
 pub fn parse_manifest(path: &Path) -> anyhow::Result<ParsedManifest> {
     let manifest = std::fs::read_to_string(path).context("Cannot read Cargo.toml manifest")?;
     let manifest = manifest
@@ -117,7 +739,108 @@ pub fn parse_manifest(path: &Path) -> anyhow::Result<ParsedManifest> {
         Default::default()
     };
     Ok(ParsedManifest {
+        path: path.to_path_buf(),
         profiles,
         document: manifest,
+        pending_state_change: None,
     })
 }
+
+/// Finds the manifest of the workspace that `manifest_path` belongs to, by walking up its
+/// ancestor directories and returning the first `Cargo.toml` that contains a `[workspace]` table.
+/// If no such manifest is found, `manifest_path` itself is returned (it is not part of a
+/// workspace with a separate root).
+pub fn find_workspace_manifest_path(manifest_path: &Path) -> anyhow::Result<PathBuf> {
+    let mut dir = manifest_path
+        .parent()
+        .context("Cargo.toml path has no parent directory")?;
+
+    loop {
+        let candidate = dir.join("Cargo.toml");
+        if candidate.exists() && parse_manifest(&candidate)?.is_workspace() {
+            return Ok(candidate);
+        }
+
+        dir = match dir.parent() {
+            Some(parent) => parent,
+            None => return Ok(manifest_path.to_path_buf()),
+        };
+    }
+}
+
+/// Returns `true` if `member_manifest` is an actual member of the workspace rooted at
+/// `workspace_root_manifest` (parsed from `workspace_root_path`), i.e. its directory matches one
+/// of `[workspace.members]`'s globs and none of `[workspace.exclude]`'s. A package can be nested
+/// under a workspace root's directory without being one of its members, in which case it must be
+/// treated as standalone rather than refused.
+fn is_workspace_member(
+    workspace_root_manifest: &ParsedManifest,
+    workspace_root_path: &Path,
+    member_manifest: &Path,
+) -> anyhow::Result<bool> {
+    let root_dir = workspace_root_path
+        .parent()
+        .context("Workspace Cargo.toml has no parent directory")?;
+    let member_dir = member_manifest
+        .parent()
+        .context("Member Cargo.toml has no parent directory")?;
+
+    if root_dir == member_dir {
+        return Ok(true);
+    }
+
+    let Some(relative_dir) = member_dir.strip_prefix(root_dir).ok() else {
+        return Ok(false);
+    };
+    let relative_dir = relative_dir.to_string_lossy().replace('\\', "/");
+
+    let Some(workspace_table) = workspace_root_manifest
+        .document
+        .get("workspace")
+        .and_then(|item| item.as_table_like())
+    else {
+        return Ok(false);
+    };
+
+    let globs = |key: &str| -> Vec<String> {
+        workspace_table
+            .get(key)
+            .and_then(|item| item.as_array())
+            .map(|array| {
+                array
+                    .iter()
+                    .filter_map(|item| item.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let matches_any = |globs: &[String]| {
+        globs
+            .iter()
+            .any(|glob| workspace_glob_matches(glob, &relative_dir))
+    };
+
+    if matches_any(&globs("exclude")) {
+        return Ok(false);
+    }
+
+    Ok(matches_any(&globs("members")))
+}
+
+/// Matches a `[workspace.members]`/`[workspace.exclude]` glob (e.g. `"crates/*"`) against a
+/// `/`-separated path relative to the workspace root. Only supports `*` as a whole path segment
+/// (matching any single segment), which covers the common member-listing patterns; it does not
+/// implement the full glob syntax cargo accepts.
+fn workspace_glob_matches(glob: &str, relative_path: &str) -> bool {
+    let glob_segments: Vec<&str> = glob.split('/').collect();
+    let path_segments: Vec<&str> = relative_path.split('/').collect();
+
+    glob_segments.len() == path_segments.len()
+        && glob_segments
+            .iter()
+            .zip(path_segments.iter())
+            .all(|(glob_segment, path_segment)| {
+                *glob_segment == "*" || glob_segment == path_segment
+            })
+}
@@ -229,6 +229,433 @@ codegen-units    = 10
     Ok(())
 }
 
+#[test]
+fn apply_custom_inherits_custom_parent() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[profile.release-lto]
+inherits = "release"
+lto = true
+"#,
+    );
+
+    project
+        .cmd(&[
+            "apply",
+            "fast-compile",
+            "custom1",
+            "--inherits",
+            "release-lto",
+        ])
+        .run()?
+        .assert_ok();
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+
+    [profile.release-lto]
+    inherits = "release"
+    lto = true
+
+    [profile.custom1]
+    inherits = "release-lto"
+    debug = 0
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn apply_custom_inherits_cycle() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[profile.a]
+inherits = "b"
+
+[profile.b]
+inherits = "a"
+"#,
+    );
+
+    project
+        .cmd(&["apply", "fast-compile", "custom1", "--inherits", "a"])
+        .run()?
+        .assert_error();
+
+    Ok(())
+}
+
+#[test]
+fn apply_refuses_member_manifest_when_workspace_root_exists() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.file(
+        "bar/Cargo.toml",
+        r#"
+[package]
+name = "bar"
+version = "0.1.0"
+edition = "2021"
+"#,
+    );
+    project.file("bar/src/lib.rs", "");
+    project.manifest(
+        r#"
+[workspace]
+members = ["bar"]
+"#,
+    );
+
+    project
+        .cmd(&[
+            "apply",
+            "fast-compile",
+            "dev",
+            "--manifest-path",
+            "bar/Cargo.toml",
+        ])
+        .run()?
+        .assert_error();
+
+    Ok(())
+}
+
+#[test]
+fn apply_refuses_member_manifest_when_workspace_root_is_not_virtual() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.file(
+        "bar/Cargo.toml",
+        r#"
+[package]
+name = "bar"
+version = "0.1.0"
+edition = "2021"
+"#,
+    );
+    project.file("bar/src/lib.rs", "");
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[workspace]
+members = ["bar"]
+"#,
+    );
+
+    project
+        .cmd(&[
+            "apply",
+            "fast-compile",
+            "dev",
+            "--manifest-path",
+            "bar/Cargo.toml",
+        ])
+        .run()?
+        .assert_error();
+
+    Ok(())
+}
+
+#[test]
+fn apply_allows_package_nested_under_unrelated_workspace_root() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+    project.file(
+        "other/Cargo.toml",
+        r#"
+[package]
+name = "other"
+version = "0.1.0"
+edition = "2021"
+"#,
+    );
+    project.file("other/src/lib.rs", "");
+    project.manifest(
+        r#"
+[workspace]
+members = ["bar"]
+"#,
+    );
+
+    project
+        .cmd(&[
+            "apply",
+            "fast-compile",
+            "dev",
+            "--manifest-path",
+            "other/Cargo.toml",
+        ])
+        .run()?
+        .assert_ok();
+    insta::assert_snapshot!(project.read("other/Cargo.toml"), @r###"
+
+    [package]
+    name = "other"
+    version = "0.1.0"
+    edition = "2021"
+
+    [profile.dev]
+    debug = 0
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn revert_custom_profile_removes_created_table() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    project
+        .cmd(&["apply", "fast-compile", "custom1"])
+        .run()?
+        .assert_ok();
+    project.cmd(&["revert", "custom1"]).run()?.assert_ok();
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn revert_after_reapplying_custom_profile_removes_created_table() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    project
+        .cmd(&["apply", "fast-compile", "custom1"])
+        .run()?
+        .assert_ok();
+    project
+        .cmd(&["apply", "fast-compile", "custom1"])
+        .run()?
+        .assert_ok();
+    project.cmd(&["revert", "custom1"]).run()?.assert_ok();
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn revert_builtin_profile_restores_overwritten_value() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[profile.dev]
+debug = 1
+"#,
+    );
+
+    project
+        .cmd(&["apply", "fast-compile", "dev"])
+        .run()?
+        .assert_ok();
+    project.cmd(&["revert", "dev"]).run()?.assert_ok();
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+
+    [profile.dev]
+    debug = 1
+    "###);
+
+    Ok(())
+}
+
+#[test]
+fn verify_matching_profile_succeeds() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[profile.dev]
+debug = 0
+"#,
+    );
+
+    project
+        .cmd(&["verify", "fast-compile", "dev"])
+        .run()?
+        .assert_ok();
+
+    Ok(())
+}
+
+#[test]
+fn verify_missing_field_fails() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    project
+        .cmd(&["verify", "fast-compile", "dev"])
+        .run()?
+        .assert_error();
+
+    Ok(())
+}
+
+#[test]
+fn verify_mismatched_field_fails() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[profile.dev]
+debug = 1
+"#,
+    );
+
+    project
+        .cmd(&["verify", "fast-compile", "dev"])
+        .run()?
+        .assert_error();
+
+    Ok(())
+}
+
+#[test]
+fn verify_bad_inherits_fails() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[profile.custom1]
+inherits = "dev"
+debug = 1
+"#,
+    );
+
+    project
+        .cmd(&["verify", "fast-compile", "custom1", "--inherits", "release"])
+        .run()?
+        .assert_error();
+
+    Ok(())
+}
+
+#[test]
+fn verify_missing_package_override_fails() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[profile.custom]
+inherits = "release"
+"#,
+    );
+
+    project
+        .cmd(&["verify", "optimize-deps", "custom"])
+        .run()?
+        .assert_error();
+
+    Ok(())
+}
+
+#[test]
+fn verify_matching_package_override_succeeds() -> anyhow::Result<()> {
+    let mut project = init_cargo_project()?;
+
+    project.manifest(
+        r#"
+[package]
+name = "foo"
+version = "0.1.0"
+edition = "2021"
+
+[profile.custom]
+inherits = "release"
+
+[profile.custom.package."*"]
+opt-level = 3
+
+[profile.custom.build-override]
+opt-level = 3
+"#,
+    );
+
+    project
+        .cmd(&["verify", "optimize-deps", "custom"])
+        .run()?
+        .assert_ok();
+
+    Ok(())
+}
+
+#[test]
+fn apply_strict_lints_template() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    project.cmd(&["apply-lints", "strict"]).run()?.assert_ok();
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+
+    [lints.rust]
+    unsafe_op_in_unsafe_fn = "deny"
+
+    [lints.clippy]
+    pedantic = "warn"
+    nursery = "warn"
+    "###);
+
+    Ok(())
+}
+
 #[test]
 fn apply_fast_runtime_template() -> anyhow::Result<()> {
     let project = init_cargo_project()?;
@@ -286,3 +713,31 @@ fn apply_min_size_template() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn apply_optimize_deps_template() -> anyhow::Result<()> {
+    let project = init_cargo_project()?;
+
+    project
+        .cmd(&["apply", "optimize-deps", "custom"])
+        .run()?
+        .assert_ok();
+    insta::assert_snapshot!(project.read_manifest(), @r###"
+
+    [package]
+    name = "foo"
+    version = "0.1.0"
+    edition = "2021"
+
+    [profile.custom]
+    inherits = "release"
+
+    [profile.custom.package."*"]
+    opt-level = 3
+
+    [profile.custom.build-override]
+    opt-level = 3
+    "###);
+
+    Ok(())
+}